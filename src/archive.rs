@@ -0,0 +1,609 @@
+/*
+ * This file is part of libespm
+ *
+ * Copyright (C) 2017 Oliver Hamlet
+ *
+ * libespm is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * libespm is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with libespm. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+// Every archive format handled here other than an uncompressed TES3 BSA
+// needs `decompress_zlib`, so unlike `subrecord`'s parsers this module has
+// no `no_std` story of its own: it should be declared `#[cfg(feature =
+// "std")] mod archive;` in lib.rs rather than split further.
+extern crate nom;
+
+use std::fmt;
+use std::str;
+
+use nom::le_u32;
+use nom::le_u64;
+use nom::IResult;
+
+use subrecord::decompress_zlib;
+use subrecord::read_u32_le;
+use subrecord::DecompressError;
+
+const TES3_VERSION: u32 = 0x100;
+const TES4_BSA_MAGIC: &[u8; 4] = b"BSA\0";
+const FALLOUT4_BA2_MAGIC: &[u8; 4] = b"BTDX";
+const FALLOUT4_BA2_GENERAL_TYPE: &[u8; 4] = b"GNRL";
+// Only referenced by the regression test confirming that archives of this
+// type are deliberately left unrecognised (see `guess_archive_format`).
+#[cfg(test)]
+const FALLOUT4_BA2_TEXTURE_TYPE: &[u8; 4] = b"DX10";
+
+const TES4_BSA_COMPRESSED_FLAG: u32 = 0x4;
+const TES4_BSA_FILE_SIZE_FLIP_COMPRESSION_BIT: u32 = 0x4000_0000;
+const TES4_BSA_FILE_SIZE_MASK: u32 = !TES4_BSA_FILE_SIZE_FLIP_COMPRESSION_BIT;
+
+/// The container format a [`Archive`] was opened as, as identified by
+/// `guess_archive_format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    /// The uncompressed Morrowind `.bsa` layout.
+    Tes3Bsa,
+    /// The zlib-compressible Oblivion/Skyrim `.bsa` layout.
+    Tes4Bsa,
+    /// The "general" (zlib-compressible) Fallout 4 / Starfield `.ba2` layout.
+    Fallout4Ba2General,
+}
+
+/// Errors that can occur while reading an archive.
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// The archive's magic bytes didn't match any known format.
+    UnrecognisedFormat,
+    /// The archive's header or file records were truncated or otherwise
+    /// malformed.
+    Malformed(&'static str),
+    /// A file's data could not be decompressed.
+    Decompress(DecompressError),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArchiveError::UnrecognisedFormat => write!(f, "unrecognised archive format"),
+            ArchiveError::Malformed(what) => write!(f, "malformed archive: {}", what),
+            ArchiveError::Decompress(e) => write!(f, "failed to decompress archive entry: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ArchiveError::Decompress(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecompressError> for ArchiveError {
+    fn from(error: DecompressError) -> Self {
+        ArchiveError::Decompress(error)
+    }
+}
+
+/// Guess the format of an archive from its magic bytes, for callers that
+/// have a file of unknown origin. Morrowind's `.bsa` layout has no magic
+/// number, so it's instead recognised by its leading version word, which is
+/// `0x100` in every released archive.
+///
+/// Fallout 4 / Starfield's chunked `DX10` texture `.ba2` layout isn't
+/// supported yet: it stores each file's mip levels as separate chunks
+/// instead of one contiguous blob, and reassembling those into a single DDS
+/// file is left to a future change. Such archives are deliberately reported
+/// as unrecognised here rather than being opened successfully only to fail
+/// on every call to `entries`.
+pub fn guess_archive_format(bytes: &[u8]) -> Option<ArchiveKind> {
+    // 12 bytes covers the TES3 version word and the BA2 magic plus its
+    // archive type, the two longest prefixes sniffed below.
+    if bytes.len() < 12 {
+        return None;
+    }
+
+    if &bytes[0..4] == TES4_BSA_MAGIC {
+        return Some(ArchiveKind::Tes4Bsa);
+    }
+
+    if &bytes[0..4] == FALLOUT4_BA2_MAGIC {
+        return match &bytes[8..12] {
+            t if t == FALLOUT4_BA2_GENERAL_TYPE => Some(ArchiveKind::Fallout4Ba2General),
+            _ => None,
+        };
+    }
+
+    if read_u32_le(&bytes[0..4]) == TES3_VERSION {
+        return Some(ArchiveKind::Tes3Bsa);
+    }
+
+    None
+}
+
+/// How an `ArchiveEntry`'s bytes are stored.
+enum EntryCompression {
+    /// Stored as-is.
+    None,
+    /// Zlib data prefixed with a 4-byte little-endian uncompressed size, as
+    /// used by compressed TES4/Skyrim BSA files.
+    ZlibWithSizePrefix,
+    /// Zlib data with no prefix, its uncompressed size instead coming from
+    /// the archive's own file record, as used by Fallout 4 BA2 files.
+    Zlib { unpacked_size: u32 },
+}
+
+/// A single file stored in an archive. `read` is lazy: opening an archive
+/// and listing its entries doesn't decompress anything, so callers that
+/// only care about a handful of paths don't pay for the rest.
+pub struct ArchiveEntry<'a> {
+    pub path: String,
+    data: &'a [u8],
+    compression: EntryCompression,
+}
+
+impl<'a> ArchiveEntry<'a> {
+    /// Get this entry's bytes, transparently decompressing them if the
+    /// archive stores them compressed.
+    pub fn read(&self) -> Result<Vec<u8>, ArchiveError> {
+        let (compressed, expected_size) = match self.compression {
+            EntryCompression::None => return Ok(self.data.to_vec()),
+            EntryCompression::ZlibWithSizePrefix => {
+                let size_prefix = self
+                    .data
+                    .get(0..4)
+                    .ok_or(ArchiveError::Malformed("archive entry's compressed data is truncated"))?;
+                (&self.data[4..], read_u32_le(size_prefix))
+            }
+            EntryCompression::Zlib { unpacked_size } => (self.data, unpacked_size),
+        };
+
+        let decompressed = decompress_zlib(compressed)?;
+
+        if decompressed.len() as u32 != expected_size {
+            return Err(ArchiveError::Decompress(DecompressError::SizeMismatch {
+                expected: expected_size,
+                actual: decompressed.len() as u32,
+            }));
+        }
+
+        Ok(decompressed)
+    }
+}
+
+/// An iterator over an archive's entries, as returned by
+/// `Archive::entries`, which reads every file record up front. The entries
+/// themselves are cheap to produce; only `ArchiveEntry::read` does any
+/// decompression.
+pub struct ArchiveEntries<'a> {
+    entries: std::vec::IntoIter<ArchiveEntry<'a>>,
+}
+
+impl<'a> Iterator for ArchiveEntries<'a> {
+    type Item = ArchiveEntry<'a>;
+
+    fn next(&mut self) -> Option<ArchiveEntry<'a>> {
+        self.entries.next()
+    }
+}
+
+/// A Bethesda archive (`.bsa` or `.ba2`), opened for reading.
+pub struct Archive<'a> {
+    kind: ArchiveKind,
+    data: &'a [u8],
+}
+
+impl<'a> Archive<'a> {
+    /// Open `data` as an archive, guessing its format from its magic bytes.
+    pub fn open(data: &'a [u8]) -> Result<Archive<'a>, ArchiveError> {
+        let kind = guess_archive_format(data).ok_or(ArchiveError::UnrecognisedFormat)?;
+
+        Ok(Archive { kind, data })
+    }
+
+    pub fn kind(&self) -> ArchiveKind {
+        self.kind
+    }
+
+    /// Enumerate this archive's entries.
+    pub fn entries(&self) -> Result<ArchiveEntries<'a>, ArchiveError> {
+        let entries = match self.kind {
+            ArchiveKind::Tes3Bsa => tes3_entries(self.data)?,
+            ArchiveKind::Tes4Bsa => tes4_entries(self.data)?,
+            ArchiveKind::Fallout4Ba2General => ba2_general_entries(self.data)?,
+        };
+
+        Ok(ArchiveEntries {
+            entries: entries.into_iter(),
+        })
+    }
+}
+
+fn read_name_block(data: &[u8], offsets: &[u32], base: usize) -> Result<Vec<String>, ArchiveError> {
+    offsets
+        .iter()
+        .map(|&offset| {
+            let start = base + offset as usize;
+            let name_bytes = data
+                .get(start..)
+                .and_then(|slice| slice.split(|&b| b == 0).next())
+                .ok_or(ArchiveError::Malformed("file name ran past the end of the archive"))?;
+
+            Ok(str::from_utf8(name_bytes)
+                .map(String::from)
+                .unwrap_or_else(|_| String::from_utf8_lossy(name_bytes).into_owned()))
+        })
+        .collect()
+}
+
+fn tes3_entries(data: &[u8]) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    const HEADER_LENGTH: usize = 12;
+
+    if data.len() < HEADER_LENGTH {
+        return Err(ArchiveError::Malformed("TES3 archive header is truncated"));
+    }
+
+    let hash_table_offset = read_u32_le(&data[4..8]) as usize;
+    let file_count = read_u32_le(&data[8..12]) as usize;
+
+    let file_records_start = HEADER_LENGTH;
+    let file_records_end = file_records_start + file_count * 8;
+    let name_offsets_end = file_records_end + file_count * 4;
+    let name_block_start = name_offsets_end;
+    let name_block_end = HEADER_LENGTH + hash_table_offset;
+
+    let file_records = data
+        .get(file_records_start..file_records_end)
+        .ok_or(ArchiveError::Malformed("TES3 file records are truncated"))?;
+    let name_offsets = data
+        .get(file_records_end..name_offsets_end)
+        .ok_or(ArchiveError::Malformed("TES3 file name offsets are truncated"))?;
+    if name_block_end < name_block_start || data.len() < name_block_end {
+        return Err(ArchiveError::Malformed("TES3 file name block is truncated"));
+    }
+
+    let name_offsets: Vec<u32> = name_offsets.chunks(4).map(read_u32_le).collect();
+    let names = read_name_block(data, &name_offsets, name_block_start)?;
+
+    let file_data_start = name_block_end + file_count * 8; // skip the hash table
+
+    let mut entries = Vec::with_capacity(file_count);
+    for (i, path) in names.into_iter().enumerate() {
+        let record = &file_records[i * 8..i * 8 + 8];
+        let size = read_u32_le(&record[0..4]) as usize;
+        let offset = read_u32_le(&record[4..8]) as usize;
+
+        let start = file_data_start + offset;
+        let file_data = data
+            .get(start..start + size)
+            .ok_or(ArchiveError::Malformed("TES3 file data ran past the end of the archive"))?;
+
+        entries.push(ArchiveEntry {
+            path,
+            data: file_data,
+            compression: EntryCompression::None,
+        });
+    }
+
+    Ok(entries)
+}
+
+struct Tes4FolderRecord {
+    file_count: u32,
+    // The record's offset field isn't kept: it isn't a direct index of the
+    // folder's file records within `data`, and the walk below reaches those
+    // records by reading them in order instead.
+}
+
+fn tes4_folder_record(input: &[u8]) -> IResult<&[u8], Tes4FolderRecord> {
+    do_parse!(input,
+        le_u64 >> // name hash, not needed to enumerate files
+        file_count: le_u32 >>
+        le_u32 >> // offset, not needed as records are walked in order
+
+        (Tes4FolderRecord { file_count })
+    )
+}
+
+fn tes4_entries(data: &[u8]) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    const HEADER_LENGTH: usize = 36;
+
+    if data.len() < HEADER_LENGTH {
+        return Err(ArchiveError::Malformed("TES4 archive header is truncated"));
+    }
+
+    let archive_flags = read_u32_le(&data[12..16]);
+    let folder_count = read_u32_le(&data[16..20]) as usize;
+    let file_count = read_u32_le(&data[20..24]) as usize;
+    let default_compressed = archive_flags & TES4_BSA_COMPRESSED_FLAG != 0;
+
+    let mut cursor = &data[HEADER_LENGTH..];
+    let mut folder_file_counts = Vec::with_capacity(folder_count);
+    for _ in 0..folder_count {
+        let (remainder, folder) =
+            tes4_folder_record(cursor).map_err(|_| ArchiveError::Malformed("TES4 folder record is truncated"))?;
+        folder_file_counts.push(folder.file_count);
+        cursor = remainder;
+    }
+
+    struct RawFileRecord {
+        size: u32,
+        offset: u32,
+    }
+
+    let mut file_records = Vec::with_capacity(file_count);
+    for &count in &folder_file_counts {
+        // Skip the folder's BZString name, which is included if
+        // IncludeDirectoryNames (0x1) is set, and precedes that folder's
+        // file records.
+        if archive_flags & 0x1 != 0 {
+            let name_length = *cursor
+                .first()
+                .ok_or(ArchiveError::Malformed("TES4 folder name is truncated"))? as usize;
+            cursor = cursor
+                .get(1 + name_length..)
+                .ok_or(ArchiveError::Malformed("TES4 folder name is truncated"))?;
+        }
+
+        for _ in 0..count {
+            // name hash (8 bytes), size (4 bytes), offset (4 bytes).
+            let record = cursor
+                .get(0..16)
+                .ok_or(ArchiveError::Malformed("TES4 file record is truncated"))?;
+            file_records.push(RawFileRecord {
+                size: read_u32_le(&record[8..12]),
+                offset: read_u32_le(&record[12..16]),
+            });
+            cursor = &cursor[16..];
+        }
+    }
+
+    let mut names = Vec::with_capacity(file_count);
+    for _ in 0..file_count {
+        let name_bytes = cursor
+            .split(|&b| b == 0)
+            .next()
+            .ok_or(ArchiveError::Malformed("TES4 file name is truncated"))?;
+        names.push(String::from_utf8_lossy(name_bytes).into_owned());
+        cursor = cursor
+            .get(name_bytes.len() + 1..)
+            .ok_or(ArchiveError::Malformed("TES4 file name is truncated"))?;
+    }
+
+    let mut entries = Vec::with_capacity(file_count);
+    for (record, path) in file_records.into_iter().zip(names.into_iter()) {
+        let is_compressed = default_compressed ^ (record.size & TES4_BSA_FILE_SIZE_FLIP_COMPRESSION_BIT != 0);
+        let size = (record.size & TES4_BSA_FILE_SIZE_MASK) as usize;
+        let offset = record.offset as usize;
+
+        let file_data = data
+            .get(offset..offset + size)
+            .ok_or(ArchiveError::Malformed("TES4 file data ran past the end of the archive"))?;
+
+        entries.push(ArchiveEntry {
+            path,
+            data: file_data,
+            compression: if is_compressed {
+                EntryCompression::ZlibWithSizePrefix
+            } else {
+                EntryCompression::None
+            },
+        });
+    }
+
+    Ok(entries)
+}
+
+fn ba2_general_entries(data: &[u8]) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    const HEADER_LENGTH: usize = 24;
+    const RECORD_LENGTH: usize = 36;
+
+    if data.len() < HEADER_LENGTH {
+        return Err(ArchiveError::Malformed("BA2 archive header is truncated"));
+    }
+
+    let file_count = read_u32_le(&data[12..16]) as usize;
+    let names_offset = read_u64_le(&data[16..24]) as usize;
+
+    let records_start = HEADER_LENGTH;
+    let records_end = records_start + file_count * RECORD_LENGTH;
+    let records = data
+        .get(records_start..records_end)
+        .ok_or(ArchiveError::Malformed("BA2 file records are truncated"))?;
+
+    let mut names = Vec::with_capacity(file_count);
+    let mut cursor = data
+        .get(names_offset..)
+        .ok_or(ArchiveError::Malformed("BA2 name table offset is out of range"))?;
+    for _ in 0..file_count {
+        let length = read_u16_le(cursor
+            .get(0..2)
+            .ok_or(ArchiveError::Malformed("BA2 name table is truncated"))?) as usize;
+        let name_bytes = cursor
+            .get(2..2 + length)
+            .ok_or(ArchiveError::Malformed("BA2 name table is truncated"))?;
+        names.push(String::from_utf8_lossy(name_bytes).into_owned());
+        cursor = &cursor[2 + length..];
+    }
+
+    let mut entries = Vec::with_capacity(file_count);
+    for (i, path) in names.into_iter().enumerate() {
+        let record = &records[i * RECORD_LENGTH..i * RECORD_LENGTH + RECORD_LENGTH];
+        let offset = read_u64_le(&record[16..24]) as usize;
+        let packed_size = read_u32_le(&record[24..28]) as usize;
+        let unpacked_size = read_u32_le(&record[28..32]) as usize;
+
+        let is_compressed = packed_size != 0;
+        let size = if is_compressed { packed_size } else { unpacked_size };
+
+        let file_data = data
+            .get(offset..offset + size)
+            .ok_or(ArchiveError::Malformed("BA2 file data ran past the end of the archive"))?;
+
+        entries.push(ArchiveEntry {
+            path,
+            data: file_data,
+            compression: if is_compressed {
+                EntryCompression::Zlib {
+                    unpacked_size: unpacked_size as u32,
+                }
+            } else {
+                EntryCompression::None
+            },
+        });
+    }
+
+    Ok(entries)
+}
+
+fn read_u16_le(bytes: &[u8]) -> u16 {
+    u16::from(bytes[0]) | u16::from(bytes[1]) << 8
+}
+
+fn read_u64_le(bytes: &[u8]) -> u64 {
+    (0..8).fold(0u64, |acc, i| acc | u64::from(bytes[i]) << (i * 8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guess_archive_format_should_recognise_a_tes4_bsa() {
+        let mut bytes = vec![0u8; 12];
+        bytes[0..4].copy_from_slice(TES4_BSA_MAGIC);
+
+        assert_eq!(Some(ArchiveKind::Tes4Bsa), guess_archive_format(&bytes));
+    }
+
+    #[test]
+    fn guess_archive_format_should_recognise_a_fallout4_ba2_general_archive() {
+        let mut bytes = vec![0u8; 12];
+        bytes[0..4].copy_from_slice(FALLOUT4_BA2_MAGIC);
+        bytes[8..12].copy_from_slice(FALLOUT4_BA2_GENERAL_TYPE);
+
+        assert_eq!(Some(ArchiveKind::Fallout4Ba2General), guess_archive_format(&bytes));
+    }
+
+    #[test]
+    fn guess_archive_format_should_return_none_for_a_fallout4_ba2_texture_archive() {
+        let mut bytes = vec![0u8; 12];
+        bytes[0..4].copy_from_slice(FALLOUT4_BA2_MAGIC);
+        bytes[8..12].copy_from_slice(FALLOUT4_BA2_TEXTURE_TYPE);
+
+        assert_eq!(None, guess_archive_format(&bytes));
+    }
+
+    #[test]
+    fn guess_archive_format_should_return_none_for_a_truncated_ba2_buffer() {
+        let mut bytes = vec![0u8; 10];
+        bytes[0..4].copy_from_slice(FALLOUT4_BA2_MAGIC);
+
+        assert_eq!(None, guess_archive_format(&bytes));
+    }
+
+    #[test]
+    fn guess_archive_format_should_recognise_a_tes3_bsa_by_its_version_word() {
+        let bytes: Vec<u8> = vec![0x00, 0x01, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        assert_eq!(Some(ArchiveKind::Tes3Bsa), guess_archive_format(&bytes));
+    }
+
+    #[test]
+    fn guess_archive_format_should_return_none_for_unrecognised_data() {
+        let bytes: Vec<u8> = vec![0xFF; 12];
+
+        assert_eq!(None, guess_archive_format(&bytes));
+    }
+
+    #[test]
+    fn archive_open_should_error_for_unrecognised_data() {
+        let bytes: Vec<u8> = vec![0xFF; 12];
+
+        assert!(Archive::open(&bytes).is_err());
+    }
+
+    #[test]
+    fn tes3_entries_should_enumerate_an_uncompressed_archive() {
+        // One file, "x", containing a single byte 0x2A.
+        let name_block = b"x\0";
+        let file_record: [u8; 8] = {
+            let mut r = [0u8; 8];
+            r[0..4].copy_from_slice(&1u32.to_le_bytes()); // size
+            r[4..8].copy_from_slice(&0u32.to_le_bytes()); // offset
+            r
+        };
+        let name_offset: [u8; 4] = 0u32.to_le_bytes();
+        let hash_table_len = 8u64.to_le_bytes()[0..8].to_vec(); // one 8-byte hash
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&TES3_VERSION.to_le_bytes());
+        let hash_table_offset = (8 + 4 + name_block.len()) as u32;
+        bytes.extend_from_slice(&hash_table_offset.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes()); // file count
+        bytes.extend_from_slice(&file_record);
+        bytes.extend_from_slice(&name_offset);
+        bytes.extend_from_slice(name_block);
+        bytes.extend_from_slice(&hash_table_len);
+        bytes.push(0x2A); // file data
+
+        let archive = Archive::open(&bytes).unwrap();
+        assert_eq!(ArchiveKind::Tes3Bsa, archive.kind());
+
+        let entries: Vec<_> = archive.entries().unwrap().collect();
+        assert_eq!(1, entries.len());
+        assert_eq!("x", entries[0].path);
+        assert_eq!(vec![0x2A], entries[0].read().unwrap());
+    }
+
+    #[test]
+    fn tes4_entries_should_enumerate_an_uncompressed_archive() {
+        // One folder containing one file, "x", containing a single byte
+        // 0x5A, with no directory names and no compression.
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(TES4_BSA_MAGIC);
+        bytes.extend_from_slice(&0x68u32.to_le_bytes()); // version
+        bytes.extend_from_slice(&36u32.to_le_bytes()); // folder records offset
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // archive flags
+        bytes.extend_from_slice(&1u32.to_le_bytes()); // folder count
+        bytes.extend_from_slice(&1u32.to_le_bytes()); // file count
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // total folder name length
+        bytes.extend_from_slice(&2u32.to_le_bytes()); // total file name length
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // file flags
+        assert_eq!(36, bytes.len());
+
+        bytes.extend_from_slice(&0u64.to_le_bytes()); // folder name hash
+        bytes.extend_from_slice(&1u32.to_le_bytes()); // folder file count
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // folder offset, unused
+
+        bytes.extend_from_slice(&0u64.to_le_bytes()); // file name hash
+
+        let name_block: &[u8] = b"x\0";
+        // +4 +4 for the size/offset fields still to be pushed below.
+        let file_data_offset = bytes.len() + 4 + 4 + name_block.len();
+        bytes.extend_from_slice(&1u32.to_le_bytes()); // file size
+        bytes.extend_from_slice(&(file_data_offset as u32).to_le_bytes()); // file offset
+
+        bytes.extend_from_slice(name_block); // file name block
+        bytes.push(0x5A); // file data
+
+        let archive = Archive::open(&bytes).unwrap();
+        assert_eq!(ArchiveKind::Tes4Bsa, archive.kind());
+
+        let entries: Vec<_> = archive.entries().unwrap().collect();
+        assert_eq!(1, entries.len());
+        assert_eq!("x", entries[0].path);
+        assert_eq!(vec![0x5A], entries[0].read().unwrap());
+    }
+}
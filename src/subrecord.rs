@@ -17,13 +17,47 @@
  * along with libespm. If not, see <http://www.gnu.org/licenses/>.
  */
 
+// The core subrecord type and its parsers only need `alloc` (for `Cow`): a
+// `no_std` caller that only wants to slice up a buffer with nom shouldn't
+// have to pull in `std::io` or flate2's std-backed `Read`/`Write` impls just
+// to link. Everything that does need actual I/O or a codec (decompression,
+// re-compression, serialization) lives behind the default-on `std` feature
+// instead; a `#![cfg_attr(not(feature = "std"), no_std)]` crate attribute in
+// lib.rs is what makes the non-`std` build of this module `no_std` in
+// practice.
 extern crate nom;
 
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+
+#[cfg(feature = "std")]
+use std::fmt;
+#[cfg(feature = "std")]
 use std::io;
+#[cfg(feature = "std")]
 use std::io::Read;
-use std::str;
-
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "std")]
+use encoding_rs::Encoding;
+#[cfg(feature = "std")]
+use encoding_rs::WINDOWS_1252;
+#[cfg(feature = "std")]
 use flate2::read::DeflateDecoder;
+#[cfg(feature = "std")]
+use flate2::read::ZlibDecoder;
+#[cfg(feature = "std")]
+use flate2::write::DeflateEncoder;
+#[cfg(feature = "std")]
+use flate2::write::ZlibEncoder;
+#[cfg(feature = "std")]
+use flate2::Compression;
 
 use nom::le_u16;
 use nom::le_u32;
@@ -32,86 +66,357 @@ use nom::IResult;
 use game_id::GameId;
 
 const SUBRECORD_TYPE_LENGTH: u8 = 4;
+#[cfg(feature = "std")]
+const XXXX_SUBRECORD_TYPE: &[u8; 4] = b"XXXX";
+
+/// The codec used to compress a subrecord's data.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionFormat {
+    /// A raw DEFLATE stream with no header or trailer, used by every game up
+    /// to and including Skyrim.
+    RawDeflate,
+    /// A zlib-framed DEFLATE stream (2-byte header, Adler-32 trailer), used
+    /// by some Fallout 4 / Starfield-era records.
+    Zlib,
+    /// An LZ4 block, used by some Fallout 4 / Starfield-era records.
+    #[cfg(feature = "lz4")]
+    Lz4Block,
+}
+
+/// Errors that can occur while decompressing a subrecord's data.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum DecompressError {
+    /// The compressed data could not be decoded, e.g. because it was
+    /// truncated or corrupt.
+    Codec(io::Error),
+    /// The decompressed data's length didn't match the expected size, e.g.
+    /// the 4-byte little-endian size prefix that precedes a compressed
+    /// subrecord's data.
+    SizeMismatch { expected: u32, actual: u32 },
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecompressError::Codec(e) => write!(f, "failed to decompress subrecord data: {}", e),
+            DecompressError::SizeMismatch { expected, actual } => write!(
+                f,
+                "decompressed subrecord data was {} bytes, expected {}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecompressError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecompressError::Codec(e) => Some(e),
+            DecompressError::SizeMismatch { .. } => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for DecompressError {
+    fn from(error: io::Error) -> Self {
+        DecompressError::Codec(error)
+    }
+}
+
+/// Work out which codec a compressed subrecord's data is encoded with.
+/// Older games only ever use a raw DEFLATE stream; Fallout 4 and later
+/// titles may use zlib framing or an LZ4 block instead, which are told apart
+/// by sniffing the first bytes of the compressed payload.
+#[cfg(feature = "std")]
+fn compression_format(game_id: GameId, compressed: &[u8]) -> CompressionFormat {
+    if game_id != GameId::Fallout4 {
+        return CompressionFormat::RawDeflate;
+    }
+
+    match compressed.get(0..2) {
+        Some(&[0x78, _]) => CompressionFormat::Zlib,
+        #[cfg(feature = "lz4")]
+        _ => CompressionFormat::Lz4Block,
+        #[cfg(not(feature = "lz4"))]
+        _ => CompressionFormat::RawDeflate,
+    }
+}
+
+pub(crate) fn read_u32_le(bytes: &[u8]) -> u32 {
+    u32::from(bytes[0])
+        | u32::from(bytes[1]) << 8
+        | u32::from(bytes[2]) << 16
+        | u32::from(bytes[3]) << 24
+}
+
+#[cfg(feature = "std")]
+fn decompress_deflate(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let mut deflater = DeflateDecoder::new(data);
+    let mut decompressed_data: Vec<u8> = Vec::new();
+    deflater.read_to_end(&mut decompressed_data)?;
+
+    Ok(decompressed_data)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn decompress_zlib(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let mut zlib = ZlibDecoder::new(data);
+    let mut decompressed_data: Vec<u8> = Vec::new();
+    zlib.read_to_end(&mut decompressed_data)?;
+
+    Ok(decompressed_data)
+}
+
+#[cfg(all(feature = "std", feature = "lz4"))]
+fn decompress_lz4_block(data: &[u8], expected_size: u32) -> Result<Vec<u8>, DecompressError> {
+    lz4_flex::block::decompress(data, expected_size as usize)
+        .map_err(|e| DecompressError::Codec(io::Error::new(io::ErrorKind::InvalidData, e)))
+}
+
+/// Get the code page that `game_id`'s plugins use to encode subrecord text by
+/// default. Creation Engine games write strings using their process's
+/// "system code page" rather than UTF-8, which for every game currently
+/// supported here is the Western European Windows-1252 page. Callers that
+/// know a plugin was authored under a different system locale (e.g. a
+/// Russian build using Windows-1251) can pass a different `Encoding` to
+/// `Subrecord::decode_data_as_string` instead of this default.
+#[cfg(feature = "std")]
+pub fn default_code_page(_game_id: GameId) -> &'static Encoding {
+    WINDOWS_1252
+}
 
+/// A subrecord borrows its data from the buffer it was parsed from wherever
+/// possible, so that parsing a plugin doesn't allocate a `Vec` for every
+/// field it contains. Use `into_owned()` to detach a `Subrecord` from the
+/// buffer it borrows from, e.g. to keep it around after the buffer goes out
+/// of scope.
 #[derive(Debug)]
-pub struct Subrecord {
-    pub subrecord_type: String,
-    pub data: Vec<u8>,
+pub struct Subrecord<'a> {
+    /// The subrecord's 4-character type, e.g. `b"CNAM"`. This is captured as
+    /// raw bytes rather than a validated `&str`: the type is always ASCII in
+    /// practice, but treating it as opaque bytes means a corrupt plugin with
+    /// a stray high byte here can't abort parsing of an otherwise readable
+    /// subrecord.
+    pub subrecord_type: [u8; 4],
+    pub data: Cow<'a, [u8]>,
     pub is_compressed: bool,
+    pub(crate) game_id: GameId,
 }
 
-impl Subrecord {
+impl<'a> Subrecord<'a> {
     pub fn new(
-        input: &[u8],
+        input: &'a [u8],
         game_id: GameId,
         data_length_override: u32,
         is_compressed: bool,
-    ) -> IResult<&[u8], Subrecord> {
+    ) -> IResult<&'a [u8], Subrecord<'a>> {
         if game_id == GameId::Morrowind {
-            morrowind_subrecord(input)
+            morrowind_subrecord(input, game_id)
         } else if data_length_override != 0 {
-            presized_subrecord(input, data_length_override, is_compressed)
+            presized_subrecord(input, game_id, data_length_override, is_compressed)
         } else {
-            simple_subrecord(input, is_compressed)
+            simple_subrecord(input, game_id, is_compressed)
         }
     }
 
-    pub fn decompress_data(&self) -> Result<Vec<u8>, io::Error> {
+    #[cfg(feature = "std")]
+    pub fn decompress_data(&self) -> Result<Vec<u8>, DecompressError> {
         if !self.is_compressed {
-            return Ok(self.data.clone());
+            return Ok(self.data.to_vec());
         }
 
-        let mut deflater = DeflateDecoder::new(&self.data[4..]);
-        let mut decompressed_data: Vec<u8> = Vec::new();
-        deflater.read_to_end(&mut decompressed_data)?;
+        let expected_size = read_u32_le(&self.data[0..4]);
+        let compressed = &self.data[4..];
+
+        let decompressed_data = match compression_format(self.game_id, compressed) {
+            CompressionFormat::RawDeflate => decompress_deflate(compressed)?,
+            CompressionFormat::Zlib => decompress_zlib(compressed)?,
+            #[cfg(feature = "lz4")]
+            CompressionFormat::Lz4Block => decompress_lz4_block(compressed, expected_size)?,
+        };
+
+        if decompressed_data.len() as u32 != expected_size {
+            return Err(DecompressError::SizeMismatch {
+                expected: expected_size,
+                actual: decompressed_data.len() as u32,
+            });
+        }
 
         Ok(decompressed_data)
     }
+
+    /// Decode `data` as text using the given code page rather than assuming
+    /// it's UTF-8, which Creation Engine plugins never use for their
+    /// strings. Use `default_code_page` to pick a sensible encoding for a
+    /// given `GameId` if the plugin's actual system locale isn't known.
+    #[cfg(feature = "std")]
+    pub fn decode_data_as_string(&self, encoding: &'static Encoding) -> Cow<str> {
+        let (string, _, _) = encoding.decode(&self.data);
+
+        string
+    }
+
+    /// Copy this subrecord's borrowed fields so that the result no longer
+    /// borrows from the buffer it was parsed from.
+    pub fn into_owned(self) -> Subrecord<'static> {
+        Subrecord {
+            subrecord_type: self.subrecord_type,
+            data: Cow::Owned(self.data.into_owned()),
+            is_compressed: self.is_compressed,
+            game_id: self.game_id,
+        }
+    }
+
+    /// Build a new compressed subrecord by compressing `data` and prepending
+    /// its uncompressed length, exactly as a compressed subrecord's data is
+    /// laid out on disk. Every game other than Fallout 4 stores a raw DEFLATE
+    /// stream; Fallout 4 is zlib-framed instead, matching what
+    /// `compression_format` sniffs for on the way back in. The resulting
+    /// `Subrecord`'s `data` can be passed to `decompress_data` to recover
+    /// `data` again, and can be written out with `write`/`to_bytes` like any
+    /// parsed subrecord.
+    #[cfg(feature = "std")]
+    pub fn compress(subrecord_type: [u8; 4], data: &[u8], game_id: GameId) -> io::Result<Subrecord<'static>> {
+        let compressed = if game_id == GameId::Fallout4 {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()?
+        } else {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()?
+        };
+
+        let mut payload = Vec::with_capacity(4 + compressed.len());
+        payload.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        payload.extend_from_slice(&compressed);
+
+        Ok(Subrecord {
+            subrecord_type,
+            data: Cow::Owned(payload),
+            is_compressed: true,
+            game_id,
+        })
+    }
+
+    /// Write this subrecord back to its on-disk representation for
+    /// `game_id`, returning the number of bytes written: the 4-byte type,
+    /// then a `u32` data length for Morrowind or a `u16` length for every
+    /// other game, then `data` itself verbatim (already DEFLATEd and
+    /// length-prefixed if `is_compressed` is set, as `compress` does). If
+    /// `data` is too long for a `u16` length field, a companion `XXXX`
+    /// subrecord carrying the true `u32` length is written first and this
+    /// subrecord's own length field is set to `0`, mirroring the
+    /// `data_length_override` handling used when reading.
+    #[cfg(feature = "std")]
+    pub fn write<W: Write>(&self, out: &mut W, game_id: GameId) -> io::Result<usize> {
+        let mut written = 0;
+        let is_oversized = game_id != GameId::Morrowind && self.data.len() > usize::from(u16::MAX);
+
+        if is_oversized {
+            written += write_xxxx_subrecord(out, self.data.len() as u32)?;
+        }
+
+        out.write_all(&self.subrecord_type)?;
+        written += self.subrecord_type.len();
+
+        if game_id == GameId::Morrowind {
+            out.write_all(&(self.data.len() as u32).to_le_bytes())?;
+            written += 4;
+        } else {
+            let length = if is_oversized { 0u16 } else { self.data.len() as u16 };
+            out.write_all(&length.to_le_bytes())?;
+            written += 2;
+        }
+
+        out.write_all(&self.data)?;
+        written += self.data.len();
+
+        Ok(written)
+    }
+
+    /// A convenience wrapper around `write` that returns the written bytes
+    /// as a new `Vec` instead of writing to a caller-supplied `Write`.
+    #[cfg(feature = "std")]
+    pub fn to_bytes(&self, game_id: GameId) -> io::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer, game_id)?;
+
+        Ok(buffer)
+    }
 }
 
-named!(subrecord_type <&str>, take_str!(SUBRECORD_TYPE_LENGTH));
+#[cfg(feature = "std")]
+fn write_xxxx_subrecord<W: Write>(out: &mut W, data_length: u32) -> io::Result<usize> {
+    out.write_all(XXXX_SUBRECORD_TYPE)?;
+    out.write_all(&4u16.to_le_bytes())?;
+    out.write_all(&data_length.to_le_bytes())?;
+
+    Ok(XXXX_SUBRECORD_TYPE.len() + 2 + 4)
+}
 
-named!(morrowind_subrecord(&[u8]) -> Subrecord,
-    do_parse!(
+fn subrecord_type(input: &[u8]) -> IResult<&[u8], [u8; 4]> {
+    map!(input, take!(SUBRECORD_TYPE_LENGTH), |bytes: &[u8]| {
+        let mut subrecord_type = [0u8; 4];
+        subrecord_type.copy_from_slice(bytes);
+        subrecord_type
+    })
+}
+
+fn morrowind_subrecord(input: &[u8], game_id: GameId) -> IResult<&[u8], Subrecord> {
+    do_parse!(input,
         subrecord_type: subrecord_type >>
         data: length_bytes!(le_u32) >>
 
         (Subrecord {
-            subrecord_type: subrecord_type.to_string(),
-            data: data.to_vec(),
+            subrecord_type,
+            data: Cow::Borrowed(data),
             is_compressed: false,
+            game_id,
         })
     )
-);
+}
 
-named_args!(simple_subrecord(is_compressed: bool) <Subrecord>,
-    do_parse!(
+fn simple_subrecord(input: &[u8], game_id: GameId, is_compressed: bool) -> IResult<&[u8], Subrecord> {
+    do_parse!(input,
         subrecord_type: subrecord_type >>
         data: length_bytes!(le_u16) >>
 
         (Subrecord {
-            subrecord_type: subrecord_type.to_string(),
-            data: data.to_vec(),
+            subrecord_type,
+            data: Cow::Borrowed(data),
             is_compressed,
+            game_id,
         })
     )
-);
+}
 
-named_args!(presized_subrecord(data_length: u32, is_compressed: bool) <Subrecord>,
-    do_parse!(
+fn presized_subrecord(
+    input: &[u8],
+    game_id: GameId,
+    data_length: u32,
+    is_compressed: bool,
+) -> IResult<&[u8], Subrecord> {
+    do_parse!(input,
         subrecord_type: subrecord_type >>
         le_u16 >>
         data: take!(data_length) >>
 
         (Subrecord {
-            subrecord_type: subrecord_type.to_string(),
-            data: data.to_vec(),
+            subrecord_type,
+            data: Cow::Borrowed(data),
             is_compressed,
+            game_id,
         })
     )
-);
+}
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
@@ -158,8 +463,11 @@ mod tests {
             .to_result()
             .unwrap();
 
-        assert_eq!("DATA", subrecord.subrecord_type);
-        assert_eq!(vec![0x6D, 0x63, 0x61, 0x72, 0x6F, 0x66, 0x61, 0x6E], subrecord.data);
+        assert_eq!(b"DATA", &subrecord.subrecord_type);
+        assert_eq!(
+            &[0x6D, 0x63, 0x61, 0x72, 0x6F, 0x66, 0x61, 0x6E][..],
+            subrecord.data.as_ref()
+        );
     }
 
     #[test]
@@ -168,8 +476,11 @@ mod tests {
             .to_result()
             .unwrap();
 
-        assert_eq!("DATA", subrecord.subrecord_type);
-        assert_eq!(vec![0x6D, 0x63, 0x61, 0x72, 0x6F, 0x66, 0x61, 0x6E], subrecord.data);
+        assert_eq!(b"DATA", &subrecord.subrecord_type);
+        assert_eq!(
+            &[0x6D, 0x63, 0x61, 0x72, 0x6F, 0x66, 0x61, 0x6E][..],
+            subrecord.data.as_ref()
+        );
     }
 
     #[test]
@@ -178,10 +489,10 @@ mod tests {
             .to_result()
             .unwrap();
 
-        assert_eq!("CNAM", subrecord.subrecord_type);
+        assert_eq!(b"CNAM", &subrecord.subrecord_type);
 
-        let expected_data = vec![0x6D, 0x63, 0x61, 0x72, 0x6F, 0x66, 0x61, 0x6E, 0x6F, 0x00];
-        assert_eq!(expected_data, subrecord.data);
+        let expected_data = [0x6D, 0x63, 0x61, 0x72, 0x6F, 0x66, 0x61, 0x6E, 0x6F, 0x00];
+        assert_eq!(&expected_data[..], subrecord.data.as_ref());
     }
 
     #[test]
@@ -190,36 +501,36 @@ mod tests {
             .to_result()
             .unwrap();
 
-        assert_eq!("CNAM", subrecord.subrecord_type);
-        assert_eq!(vec![0x6D, 0x63, 0x61, 0x72], subrecord.data);
+        assert_eq!(b"CNAM", &subrecord.subrecord_type);
+        assert_eq!(&[0x6D, 0x63, 0x61, 0x72][..], subrecord.data.as_ref());
 
         let subrecord = Subrecord::new(TES4_CNAM_SUBRECORD, GameId::Skyrim, 4, false)
             .to_result()
             .unwrap();
 
-        assert_eq!("CNAM", subrecord.subrecord_type);
-        assert_eq!(vec![0x6D, 0x63, 0x61, 0x72], subrecord.data);
+        assert_eq!(b"CNAM", &subrecord.subrecord_type);
+        assert_eq!(&[0x6D, 0x63, 0x61, 0x72][..], subrecord.data.as_ref());
 
         let subrecord = Subrecord::new(TES4_CNAM_SUBRECORD, GameId::Fallout3, 4, false)
             .to_result()
             .unwrap();
 
-        assert_eq!("CNAM", subrecord.subrecord_type);
-        assert_eq!(vec![0x6D, 0x63, 0x61, 0x72], subrecord.data);
+        assert_eq!(b"CNAM", &subrecord.subrecord_type);
+        assert_eq!(&[0x6D, 0x63, 0x61, 0x72][..], subrecord.data.as_ref());
 
         let subrecord = Subrecord::new(TES4_CNAM_SUBRECORD, GameId::FalloutNV, 4, false)
             .to_result()
             .unwrap();
 
-        assert_eq!("CNAM", subrecord.subrecord_type);
-        assert_eq!(vec![0x6D, 0x63, 0x61, 0x72], subrecord.data);
+        assert_eq!(b"CNAM", &subrecord.subrecord_type);
+        assert_eq!(&[0x6D, 0x63, 0x61, 0x72][..], subrecord.data.as_ref());
 
         let subrecord = Subrecord::new(TES4_CNAM_SUBRECORD, GameId::Fallout4, 4, false)
             .to_result()
             .unwrap();
 
-        assert_eq!("CNAM", subrecord.subrecord_type);
-        assert_eq!(vec![0x6D, 0x63, 0x61, 0x72], subrecord.data);
+        assert_eq!(b"CNAM", &subrecord.subrecord_type);
+        assert_eq!(&[0x6D, 0x63, 0x61, 0x72][..], subrecord.data.as_ref());
     }
 
     #[test]
@@ -227,7 +538,7 @@ mod tests {
         const DATA: &'static [u8] = &[
             0x42, 0x50, 0x54, 0x4E,  //field type
             0x1D, 0x00,  //field size
-            0x19, 0x00, 0x00, 0x00,  //decompressed field size
+            0x1F, 0x00, 0x00, 0x00,  //decompressed field size
             0x75, 0xc5, 0x21, 0x0d, 0x00, 0x00, 0x08, 0x05, 0xd1, 0x6c,  //field data (compressed)
             0x6c, 0xdc, 0x57, 0x48, 0x3c, 0xfd, 0x5b, 0x5c, 0x02, 0xd4,  //field data (compressed)
             0x6b, 0x32, 0xb5, 0xdc, 0xa3  //field data (compressed)
@@ -239,10 +550,54 @@ mod tests {
 
         let decompressed_data = subrecord.decompress_data().unwrap();
 
-        assert_eq!("BPTN", subrecord.subrecord_type);
+        assert_eq!(b"BPTN", &subrecord.subrecord_type);
         assert_eq!("DEFLATE_DEFLATE_DEFLATE_DEFLATE".as_bytes(), decompressed_data.as_slice());
     }
 
+    #[test]
+    fn decompress_data_should_use_zlib_for_fallout4_data_with_a_zlib_header() {
+        const DATA: &'static [u8] = &[
+            0x54, 0x45, 0x58, 0x54, //field type, "TEXT"
+            0x17, 0x00, //field size
+            0x11, 0x00, 0x00, 0x00, //decompressed field size
+            0x78, 0xda, 0x8b, 0xf2, 0xf1, 0x74, 0x0a, 0x71, 0x0d, 0x0e, 0x89, //zlib data
+            0x8f, 0x82, 0x32, 0x00, 0x2f, 0x5b, 0x05, 0x42, //zlib data
+        ];
+
+        let subrecord = Subrecord::new(DATA, GameId::Fallout4, 0, true)
+            .to_result()
+            .unwrap();
+
+        let decompressed_data = subrecord.decompress_data().unwrap();
+
+        assert_eq!(b"TEXT", &subrecord.subrecord_type);
+        assert_eq!(b"ZLIBTEST_ZLIBTEST", decompressed_data.as_slice());
+    }
+
+    #[test]
+    fn decompress_data_should_error_if_the_decompressed_size_does_not_match_the_size_prefix() {
+        const DATA: &'static [u8] = &[
+            0x42, 0x50, 0x54, 0x4E,  //field type
+            0x1D, 0x00,  //field size
+            0xFF, 0x00, 0x00, 0x00,  //decompressed field size (wrong)
+            0x75, 0xc5, 0x21, 0x0d, 0x00, 0x00, 0x08, 0x05, 0xd1, 0x6c,  //field data (compressed)
+            0x6c, 0xdc, 0x57, 0x48, 0x3c, 0xfd, 0x5b, 0x5c, 0x02, 0xd4,  //field data (compressed)
+            0x6b, 0x32, 0xb5, 0xdc, 0xa3  //field data (compressed)
+        ];
+
+        let subrecord = Subrecord::new(DATA, GameId::Skyrim, 0, true)
+            .to_result()
+            .unwrap();
+
+        match subrecord.decompress_data() {
+            Err(DecompressError::SizeMismatch { expected, actual }) => {
+                assert_eq!(0xFF, expected);
+                assert_eq!(31, actual);
+            }
+            other => panic!("expected a size mismatch error, got {:?}", other),
+        }
+    }
+
     #[test]
     fn decompress_data_should_error_if_the_compressed_data_is_invalid() {
         const DATA: &'static [u8] = &[
@@ -260,4 +615,105 @@ mod tests {
 
         assert!(subrecord.decompress_data().is_err());
     }
+
+    #[test]
+    fn into_owned_should_detach_a_subrecord_from_its_source_buffer() {
+        let owned = {
+            let buffer = TES3_DATA_SUBRECORD.to_vec();
+            let subrecord = Subrecord::new(&buffer, GameId::Morrowind, 0, false)
+                .to_result()
+                .unwrap();
+            subrecord.into_owned()
+        };
+
+        assert_eq!(b"DATA", &owned.subrecord_type);
+        assert_eq!(
+            &[0x6D, 0x63, 0x61, 0x72, 0x6F, 0x66, 0x61, 0x6E][..],
+            owned.data.as_ref()
+        );
+    }
+
+    #[test]
+    fn decode_data_as_string_should_decode_using_the_given_code_page() {
+        const NAME_SUBRECORD: &'static [u8] = &[
+            0x4E, 0x41, 0x4D, 0x45, //field type, "NAME"
+            0x04, 0x00, //field size
+            0xE9, 0x63, 0x6F, 0x6C, //field data, Windows-1252 "\xE9col"
+        ];
+
+        let subrecord = Subrecord::new(NAME_SUBRECORD, GameId::Skyrim, 0, false)
+            .to_result()
+            .unwrap();
+
+        assert_eq!(
+            "\u{e9}col",
+            subrecord.decode_data_as_string(encoding_rs::WINDOWS_1252)
+        );
+    }
+
+    #[test]
+    fn default_code_page_should_return_windows_1252_for_every_supported_game() {
+        assert_eq!(encoding_rs::WINDOWS_1252, default_code_page(GameId::Morrowind));
+        assert_eq!(encoding_rs::WINDOWS_1252, default_code_page(GameId::Skyrim));
+        assert_eq!(encoding_rs::WINDOWS_1252, default_code_page(GameId::Fallout4));
+    }
+
+    #[test]
+    fn write_should_reproduce_a_parsed_non_morrowind_subrecord() {
+        let subrecord = Subrecord::new(TES4_CNAM_SUBRECORD, GameId::Skyrim, 0, false)
+            .to_result()
+            .unwrap();
+
+        let bytes = subrecord.to_bytes(GameId::Skyrim).unwrap();
+
+        assert_eq!(TES4_CNAM_SUBRECORD, bytes.as_slice());
+    }
+
+    #[test]
+    fn write_should_reproduce_a_parsed_morrowind_subrecord() {
+        let subrecord = Subrecord::new(TES3_DATA_SUBRECORD, GameId::Morrowind, 0, false)
+            .to_result()
+            .unwrap();
+
+        let bytes = subrecord.to_bytes(GameId::Morrowind).unwrap();
+
+        assert_eq!(TES3_DATA_SUBRECORD, bytes.as_slice());
+    }
+
+    #[test]
+    fn write_should_emit_a_companion_xxxx_subrecord_for_oversized_non_morrowind_data() {
+        let data = vec![0x2A; usize::from(u16::MAX) + 1];
+        let subrecord = Subrecord {
+            subrecord_type: *b"CNAM",
+            data: Cow::Owned(data.clone()),
+            is_compressed: false,
+            game_id: GameId::Skyrim,
+        };
+
+        let bytes = subrecord.to_bytes(GameId::Skyrim).unwrap();
+
+        assert_eq!(b"XXXX", &bytes[0..4]);
+        assert_eq!(&4u16.to_le_bytes()[..], &bytes[4..6]);
+        assert_eq!(&(data.len() as u32).to_le_bytes()[..], &bytes[6..10]);
+
+        assert_eq!(b"CNAM", &bytes[10..14]);
+        assert_eq!(&0u16.to_le_bytes()[..], &bytes[14..16]);
+        assert_eq!(data.as_slice(), &bytes[16..]);
+    }
+
+    #[test]
+    fn compress_should_build_a_subrecord_that_decompresses_back_to_the_original_data() {
+        let subrecord = Subrecord::compress(*b"FULL", b"a compressible string", GameId::Skyrim).unwrap();
+
+        assert!(subrecord.is_compressed);
+        assert_eq!(b"a compressible string", subrecord.decompress_data().unwrap().as_slice());
+    }
+
+    #[test]
+    fn compress_should_build_a_subrecord_that_decompresses_back_to_the_original_data_for_fallout4() {
+        let subrecord = Subrecord::compress(*b"FULL", b"a compressible string", GameId::Fallout4).unwrap();
+
+        assert!(subrecord.is_compressed);
+        assert_eq!(b"a compressible string", subrecord.decompress_data().unwrap().as_slice());
+    }
 }